@@ -14,57 +14,129 @@
 
 //! Druid implementation of gesture recognition
 
-//use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::collections::{VecDeque, HashMap};
 
-use crate::kurbo::Point;
+use crate::kurbo::{Point, Vec2};
 
-use crate::{Event, PointerEvent, PointerId};
+use crate::{Event, PointerEvent, PointerId, WidgetId};
 
 pub trait GestureRecognizer {
     fn process_event(&mut self, event: &Event) -> VecDeque<Event>;
+
+    /// Advances the recognizer's internal clock without a new pointer event,
+    /// so that timeout-driven gestures (e.g. long-press) can fire.
+    fn tick(&mut self, now: Instant) -> VecDeque<Event>;
 }
 
+/// Tracks an in-progress gesture made of two or more fingers.
+///
+/// Pointers are kept in the stable arrival order recorded in
+/// `DruidGestureRecognizer::pointer_order`, rather than relying on `HashMap`
+/// iteration order (which is unspecified and can silently swap finger
+/// identities between frames). Zoom and rotation are only well-defined for
+/// exactly two fingers; with three or more, the gesture degrades gracefully
+/// to a pan of the centroid of all tracked fingers.
 #[derive(Debug, Clone, PartialEq)]
-struct TwoFingersGesture {
-    finger_one_id: PointerId,
-    finger_two_id: PointerId,
-
-    finger_one_pos: Point,
-    finger_two_pos: Point,
+struct MultiFingerGesture {
+    pointer_ids: Vec<PointerId>,
 
-    finger_one_pos_cur: Point,
-    finger_two_pos_cur: Point,
+    /// Position of each pointer (aligned by index with `pointer_ids`) as of
+    /// when the gesture's current baseline was captured. Zoom and rotation
+    /// deltas are measured against these.
+    initial_positions: Vec<Point>,
+    /// Position of each pointer (aligned by index with `pointer_ids`) as of
+    /// the last processed frame.
+    current_positions: Vec<Point>,
 
     zoom: f64,
+
+    /// Angle (radians) of the vector from the first to the second finger, as
+    /// of the last processed frame. Used to derive the per-frame rotation
+    /// delta the same way `zoom` is used to derive the per-frame zoom delta.
+    /// Meaningless (and left unused) when there are more than two fingers.
+    angle: f64,
+}
+
+fn centroid(positions: &[Point]) -> Point {
+    let sum = positions
+        .iter()
+        .fold(Vec2::ZERO, |acc, pos| acc + pos.to_vec2());
+    (sum / positions.len() as f64).to_point()
 }
 
-impl TwoFingersGesture {
+impl MultiFingerGesture {
     fn center(&self) -> Point {
-        self.finger_one_pos_cur.midpoint(self.finger_two_pos_cur)
+        centroid(&self.current_positions)
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+struct OneFingerGesture {
+    pointer_id: PointerId,
+
+    start_pos: Point,
+    start_time: Instant,
+
+    /// Number of taps recognized so far in the current tap sequence (1 for a
+    /// single tap, 2 for a double tap, ...).
+    tap_count: u32,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum GestureRecognizerState {
     Idle,
-    //OneFingerIdle,
-    //OneFingerPressed,
-    //OneFingerTap,
-    TwoFingersIdle(TwoFingersGesture),
-    PinchPanGesture(TwoFingersGesture),
+    OneFingerIdle(OneFingerGesture),
+    OneFingerPressed(OneFingerGesture),
+    OneFingerTap(OneFingerGesture),
+    TwoFingersIdle(MultiFingerGesture),
+    PinchPanGesture(MultiFingerGesture),
 }
 
-//const TAP_DELAY: Duration = Duration::from_millis(50);
+const TAP_DELAY: Duration = Duration::from_millis(300);
+const TAP_MOVE_TRESHOLD: f64 = 10f64;
+const LONG_PRESS_DELAY: Duration = Duration::from_millis(500);
 const TWOFINGERS_MIN_PINCH_TRESHOLD: f64 = 20f64;
 const PINCH_ZOOM_GAIN: f64 = 1f64;
 
+/// Number of trailing (timestamp, event) samples kept per pointer, used to fit
+/// a velocity estimate for `GestureFling` once the gesture ends.
+const POINTER_TRACK_MAX_SAMPLES: usize = 5;
+
 //const ZOOM_DELTA_MAX_TRESHOLD: f64 = 0.001;
 
+/// Which two-finger transforms a [`DruidGestureRecognizer`] is allowed to emit.
+///
+/// A widget that only wants to scroll, for instance, can use [`GestureMode::PanOnly`]
+/// so that an accidental change in finger spacing during a pan doesn't also produce
+/// a `GestureZoom`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GestureMode {
+    /// Only `GesturePan` is emitted; zoom and rotation deltas are discarded.
+    PanOnly,
+    /// `GesturePan` and `GestureZoom` are emitted; rotation deltas are discarded.
+    PanZoom,
+    /// `GesturePan`, `GestureZoom` and `GestureRotate` are all emitted.
+    PanZoomRotate,
+}
+
 /// Implements the state machine for recognizing gestures
 pub struct DruidGestureRecognizer {
     state: GestureRecognizerState,
-    pointers_track: HashMap<PointerId, VecDeque<Event>>,
+    pointers_track: HashMap<PointerId, VecDeque<(Instant, Event)>>,
+    mode: GestureMode,
+
+    /// Currently tracked pointers in the order they were admitted. This is
+    /// the source of truth for "finger one", "finger two", etc., so that
+    /// slot assignment is stable across frames regardless of `HashMap`
+    /// iteration order.
+    pointer_order: Vec<PointerId>,
+
+    /// Pointers claimed by a widget, keyed by pointer. Once a pointer is
+    /// tracked for a gesture, only pointers captured by the same target (or
+    /// uncaptured pointers) are admitted into that gesture; foreign pointers
+    /// are ignored rather than becoming e.g. finger two of someone else's pinch.
+    captures: HashMap<PointerId, WidgetId>,
 }
 
 fn pointer_event_unchecked(evt: &Event) -> &PointerEvent {
@@ -82,127 +154,338 @@ fn pointer_event_unchecked(evt: &Event) -> &PointerEvent {
     }
 }
 
-fn compute_zoom_level(finger_one_pos: Point, finger_two_pos: Point, gesture_state: &TwoFingersGesture) -> f64 {
-    let initial_distance = gesture_state.finger_one_pos.distance(gesture_state.finger_two_pos);
-    let current_distance = finger_one_pos.distance(finger_two_pos);
+/// Zoom level relative to `initial_positions`. Only defined for exactly two
+/// fingers; with any other count there's no single-distance notion of zoom,
+/// so the level is left unchanged (1.0, i.e. no zoom).
+fn compute_zoom_level(initial_positions: &[Point], current_positions: &[Point]) -> f64 {
+    if initial_positions.len() != 2 || current_positions.len() != 2 {
+        return 1.0;
+    }
+
+    let initial_distance = initial_positions[0].distance(initial_positions[1]);
+    let current_distance = current_positions[0].distance(current_positions[1]);
 
-    (current_distance / initial_distance)  * PINCH_ZOOM_GAIN
+    (current_distance / initial_distance) * PINCH_ZOOM_GAIN
+}
+
+/// Angle (radians) of the vector going from `finger_one_pos` to `finger_two_pos`.
+fn compute_angle(finger_one_pos: Point, finger_two_pos: Point) -> f64 {
+    (finger_two_pos.y - finger_one_pos.y).atan2(finger_two_pos.x - finger_one_pos.x)
+}
+
+/// Angle (radians) of the vector between the first two tracked fingers, or
+/// `None` if fewer than two fingers are tracked (rotation is undefined then).
+fn compute_multifinger_angle(positions: &[Point]) -> Option<f64> {
+    if positions.len() < 2 {
+        return None;
+    }
+    Some(compute_angle(positions[0], positions[1]))
+}
+
+/// Normalizes an angle (radians) into `(-PI, PI]` so that small rotations near
+/// the `atan2` branch cut don't show up as a near-full-turn jump.
+fn normalize_angle(angle: f64) -> f64 {
+    use std::f64::consts::PI;
+    let mut normalized = angle % (2f64 * PI);
+    if normalized <= -PI {
+        normalized += 2f64 * PI;
+    } else if normalized > PI {
+        normalized -= 2f64 * PI;
+    }
+    normalized
+}
+
+/// Fits a velocity estimate (pixels/sec) from a pointer's buffered samples,
+/// using the oldest and newest of the last [`POINTER_TRACK_MAX_SAMPLES`].
+fn compute_velocity(queue: &VecDeque<(Instant, Event)>) -> Option<Vec2> {
+    let (start_time, start_event) = queue.front()?;
+    let (end_time, end_event) = queue.back()?;
+
+    let elapsed = end_time.duration_since(*start_time).as_secs_f64();
+    if elapsed <= 0f64 {
+        return None;
+    }
+
+    let start_pos = pointer_event_unchecked(start_event).pos;
+    let end_pos = pointer_event_unchecked(end_event).pos;
+
+    Some((end_pos.to_vec2() - start_pos.to_vec2()) / elapsed)
 }
 
 impl DruidGestureRecognizer {
-    /// Creates a new gesture recognition state machine
-    pub fn new() -> Self {
+    /// Creates a new gesture recognition state machine that only emits the
+    /// transforms allowed by `mode`.
+    pub fn new(mode: GestureMode) -> Self {
         DruidGestureRecognizer {
             state: GestureRecognizerState::Idle,
             pointers_track: HashMap::new(),
+            mode,
+            pointer_order: Vec::new(),
+            captures: HashMap::new(),
         }
     }
 
-    fn get_current_twofinger_gesture(&self) -> TwoFingersGesture {
-        let events: Vec<(&PointerId, &VecDeque<Event>)> = self.pointers_track.iter().collect();
-        let finger_one_pos = pointer_event_unchecked(events[0].1.back().unwrap()).pos;
-        let finger_two_pos = pointer_event_unchecked(events[1].1.back().unwrap()).pos;
-        TwoFingersGesture {
-            finger_one_id: events[0].0.clone(),
-            finger_two_id: events[1].0.clone(),
+    /// Claims `id` for `target`. Once claimed, the recognizer only admits this
+    /// pointer into a shared gesture alongside pointers captured by the same
+    /// target (or not yet captured at all).
+    pub fn capture(&mut self, id: PointerId, target: WidgetId) {
+        self.captures.insert(id, target);
+    }
 
-            finger_one_pos: finger_one_pos,
-            finger_two_pos: finger_two_pos,
+    /// Releases a pointer previously claimed with [`Self::capture`].
+    pub fn release(&mut self, id: &PointerId) {
+        self.captures.remove(id);
+    }
 
-            finger_one_pos_cur: finger_one_pos,
-            finger_two_pos_cur: finger_two_pos,
+    /// The target that owns the gesture currently being tracked, if any of
+    /// its pointers have been captured.
+    fn current_target(&self) -> Option<&WidgetId> {
+        self.pointers_track.keys().find_map(|id| self.captures.get(id))
+    }
+
+    /// Whether `id` may join the gesture currently being tracked: true if none
+    /// of the tracked pointers is captured (including when none is tracked yet),
+    /// if `id` isn't captured, or if it's captured by the same target that
+    /// already owns the tracked pointers.
+    fn admits_pointer(&self, id: &PointerId) -> bool {
+        match (self.current_target(), self.captures.get(id)) {
+            (Some(current), Some(new)) => current == new,
+            _ => true,
+        }
+    }
 
+    /// Builds a gesture baseline from all currently tracked pointers, in
+    /// their stable `pointer_order`.
+    fn get_current_multifinger_gesture(&self) -> MultiFingerGesture {
+        let positions: Vec<Point> = self
+            .pointer_order
+            .iter()
+            .filter_map(|id| self.pointer_pos(id))
+            .collect();
+        MultiFingerGesture {
+            pointer_ids: self.pointer_order.clone(),
+            initial_positions: positions.clone(),
+            current_positions: positions.clone(),
             zoom: 1.0,
+            angle: compute_multifinger_angle(&positions).unwrap_or(0.0),
+        }
+    }
+
+    /// Looks up the current position of every pointer in `ids`, or `None` if
+    /// any of them is no longer tracked (e.g. it vanished mid-gesture).
+    fn current_positions_for(&self, ids: &[PointerId]) -> Option<Vec<Point>> {
+        ids.iter().map(|id| self.pointer_pos(id)).collect()
+    }
+
+    fn get_current_onefinger_gesture(&self) -> OneFingerGesture {
+        let (id, queue) = self.pointers_track.iter().next().unwrap();
+        let pos = pointer_event_unchecked(&queue.back().unwrap().1).pos;
+        OneFingerGesture {
+            pointer_id: id.clone(),
+            start_pos: pos,
+            start_time: Instant::now(),
+            tap_count: 0,
         }
     }
 
     fn pointer_pos(&self, id: &PointerId) -> Option<Point> {
         if let Some(queue) = self.pointers_track.get(id) {
-            let pos = pointer_event_unchecked(queue.back().unwrap()).pos;
+            let pos = pointer_event_unchecked(&queue.back().unwrap().1).pos;
             Some(pos)
         } else {
             None
         }
     }
 
-    /// Returns true if pointers have been added or removed
-    fn update_pointers(&mut self, event: &Event) -> bool {
+    /// Returns true if pointers have been added or removed, along with the
+    /// buffered sample queue of any pointer removed by this event (used to
+    /// fit a fling velocity once a gesture ends).
+    fn update_pointers(&mut self, event: &Event) -> (bool, Option<VecDeque<(Instant, Event)>>) {
         let mut pointers_changed = false;
+        let mut removed_queue = None;
         match event {
             Event::PointerDown(pointer_event) => {
                 if let Some(queue) = self.pointers_track.get_mut(&pointer_event.id) {
-                    queue.push_back(event.clone());
-                } else {
+                    queue.push_back((Instant::now(), event.clone()));
+                    if queue.len() > POINTER_TRACK_MAX_SAMPLES {
+                        queue.pop_front();
+                    }
+                } else if self.admits_pointer(&pointer_event.id) {
                     pointers_changed = true;
                     let mut queue = VecDeque::new();
-                    queue.push_back(event.clone());
+                    queue.push_back((Instant::now(), event.clone()));
                     self.pointers_track.insert(pointer_event.id.clone(), queue);
+                    self.pointer_order.push(pointer_event.id.clone());
                 }
+                // else: captured by a different target, ignore entirely
             },
             Event::PointerMove(pointer_event) => {
                 if let Some(queue) = self.pointers_track.get_mut(&pointer_event.id) {
-                    queue.push_back(event.clone());
+                    queue.push_back((Instant::now(), event.clone()));
+                    if queue.len() > POINTER_TRACK_MAX_SAMPLES {
+                        queue.pop_front();
+                    }
                 }
                 // discard eventual PointerMove with no previous PointerDown
             },
             Event::PointerUp(pointer_event) | Event::PointerLeave(pointer_event) => {
-                self.pointers_track.remove(&pointer_event.id);
-                pointers_changed = true;
+                if let Some(queue) = self.pointers_track.remove(&pointer_event.id) {
+                    self.pointer_order.retain(|id| id != &pointer_event.id);
+                    removed_queue = Some(queue);
+                    pointers_changed = true;
+                }
+                // A capture is only meaningful while the pointer is alive; don't rely
+                // on callers pairing every capture() with a release().
+                self.captures.remove(&pointer_event.id);
             }
             _ => {}
         };
 
-        pointers_changed
+        (pointers_changed, removed_queue)
     }
 }
 
 impl GestureRecognizer for DruidGestureRecognizer {
     fn process_event(&mut self, event: &Event) -> VecDeque<Event> {
-        let pointers_changed = self.update_pointers(&event);
+        let (pointers_changed, removed_queue) = self.update_pointers(&event);
 
         let new_state = match &self.state {
             GestureRecognizerState::Idle => {
-                if self.pointers_track.len() == 2 {
+                if self.pointers_track.len() >= 2 {
                     GestureRecognizerState::TwoFingersIdle(
-                        self.get_current_twofinger_gesture()
-                    )                    
+                        self.get_current_multifinger_gesture()
+                    )
+                } else if self.pointers_track.len() == 1 {
+                    GestureRecognizerState::OneFingerIdle(
+                        self.get_current_onefinger_gesture()
+                    )
                 } else {
                     self.state.clone()
                 }
             },
+            GestureRecognizerState::OneFingerIdle(gesture_state) => {
+                match event {
+                    Event::PointerUp(pointer_event) | Event::PointerLeave(pointer_event)
+                        if pointer_event.id == gesture_state.pointer_id =>
+                    {
+                        let distance = gesture_state.start_pos.distance(pointer_event.pos);
+                        let held_for = Instant::now().duration_since(gesture_state.start_time);
+                        if distance <= TAP_MOVE_TRESHOLD && held_for < LONG_PRESS_DELAY {
+                            let mut tapped = gesture_state.clone();
+                            tapped.tap_count += 1;
+                            tapped.start_pos = pointer_event.pos;
+                            tapped.start_time = Instant::now();
+                            GestureRecognizerState::OneFingerTap(tapped)
+                        } else {
+                            GestureRecognizerState::Idle
+                        }
+                    },
+                    Event::PointerMove(pointer_event)
+                        if pointer_event.id == gesture_state.pointer_id =>
+                    {
+                        let distance = gesture_state.start_pos.distance(pointer_event.pos);
+                        if distance > TAP_MOVE_TRESHOLD {
+                            GestureRecognizerState::Idle
+                        } else {
+                            self.state.clone()
+                        }
+                    },
+                    _ if self.pointers_track.len() >= 2 => {
+                        GestureRecognizerState::TwoFingersIdle(self.get_current_multifinger_gesture())
+                    },
+                    _ if pointers_changed => GestureRecognizerState::Idle,
+                    _ => self.state.clone(),
+                }
+            },
+            GestureRecognizerState::OneFingerPressed(gesture_state) => {
+                match event {
+                    Event::PointerUp(pointer_event) | Event::PointerLeave(pointer_event)
+                        if pointer_event.id == gesture_state.pointer_id =>
+                    {
+                        GestureRecognizerState::Idle
+                    },
+                    _ if pointers_changed => GestureRecognizerState::Idle,
+                    _ => self.state.clone(),
+                }
+            },
+            GestureRecognizerState::OneFingerTap(gesture_state) => {
+                match event {
+                    // Only a pointer `update_pointers` actually admitted continues the
+                    // tap sequence. The tapped pointer's capture is dropped on release
+                    // and no pointer is tracked while a tap is pending, so a pointer
+                    // captured by a different widget is admitted here as well.
+                    Event::PointerDown(pointer_event)
+                        if self.pointers_track.contains_key(&pointer_event.id) =>
+                    {
+                        let distance = gesture_state.start_pos.distance(pointer_event.pos);
+                        let mut pressed = gesture_state.clone();
+                        pressed.pointer_id = pointer_event.id.clone();
+                        pressed.start_pos = pointer_event.pos;
+                        pressed.start_time = Instant::now();
+                        if distance > TAP_MOVE_TRESHOLD
+                            || Instant::now().duration_since(gesture_state.start_time) >= TAP_DELAY
+                        {
+                            pressed.tap_count = 0;
+                        }
+                        GestureRecognizerState::OneFingerIdle(pressed)
+                    },
+                    _ => self.state.clone(),
+                }
+            },
             GestureRecognizerState::TwoFingersIdle(gesture_state) => {
                 if pointers_changed {
-                    GestureRecognizerState::Idle
-                } else {
-                    let finger_one_current_pos = self.pointer_pos(&gesture_state.finger_one_id);
-                    let finger_two_current_pos = self.pointer_pos(&gesture_state.finger_two_id);
-                    let finger_one_distance =
-                        gesture_state.finger_one_pos.distance(finger_one_current_pos.unwrap());
-                    let finger_two_distance =
-                        gesture_state.finger_two_pos.distance(finger_two_current_pos.unwrap());
-                    if finger_one_distance.abs() > TWOFINGERS_MIN_PINCH_TRESHOLD ||
-                       finger_two_distance.abs() > TWOFINGERS_MIN_PINCH_TRESHOLD {  
-                        GestureRecognizerState::PinchPanGesture(gesture_state.clone())
+                    if self.pointers_track.len() >= 2 {
+                        // Fingers joined or left but at least two remain: re-baseline
+                        // rather than dropping the gesture entirely.
+                        GestureRecognizerState::TwoFingersIdle(self.get_current_multifinger_gesture())
                     } else {
-                        self.state.clone()
+                        GestureRecognizerState::Idle
+                    }
+                } else {
+                    match self.current_positions_for(&gesture_state.pointer_ids) {
+                        Some(current_positions) => {
+                            let moved = gesture_state
+                                .initial_positions
+                                .iter()
+                                .zip(current_positions.iter())
+                                .any(|(initial, current)| {
+                                    initial.distance(*current) > TWOFINGERS_MIN_PINCH_TRESHOLD
+                                });
+                            if moved {
+                                let mut new_state = gesture_state.clone();
+                                new_state.current_positions = current_positions;
+                                GestureRecognizerState::PinchPanGesture(new_state)
+                            } else {
+                                self.state.clone()
+                            }
+                        },
+                        // A tracked pointer vanished without an Up/Leave event reaching
+                        // us: bail out quietly instead of panicking on a missing position.
+                        None => GestureRecognizerState::Idle,
                     }
                 }
             },
             GestureRecognizerState::PinchPanGesture(gesture_state) => {
                 if pointers_changed {
-                    GestureRecognizerState::Idle
+                    if self.pointers_track.len() >= 2 {
+                        GestureRecognizerState::PinchPanGesture(self.get_current_multifinger_gesture())
+                    } else {
+                        GestureRecognizerState::Idle
+                    }
                 } else {
-                    let finger_one_current_pos = self.pointer_pos(&gesture_state.finger_one_id);
-                    let finger_two_current_pos = self.pointer_pos(&gesture_state.finger_two_id);
-
-                    let mut new_state = gesture_state.clone();
-                    new_state.zoom = compute_zoom_level(
-                        finger_one_current_pos.unwrap(),
-                        finger_two_current_pos.unwrap(),
-                        &gesture_state);
-                    new_state.finger_one_pos_cur = finger_one_current_pos.unwrap();
-                    new_state.finger_two_pos_cur = finger_two_current_pos.unwrap();
-                    GestureRecognizerState::PinchPanGesture(new_state)
+                    match self.current_positions_for(&gesture_state.pointer_ids) {
+                        Some(current_positions) => {
+                            let mut new_state = gesture_state.clone();
+                            new_state.zoom = compute_zoom_level(
+                                &gesture_state.initial_positions,
+                                &current_positions);
+                            new_state.angle = compute_multifinger_angle(&current_positions)
+                                .unwrap_or(gesture_state.angle);
+                            new_state.current_positions = current_positions;
+                            GestureRecognizerState::PinchPanGesture(new_state)
+                        },
+                        None => GestureRecognizerState::Idle,
+                    }
                 }
             },
         };
@@ -211,16 +494,53 @@ impl GestureRecognizer for DruidGestureRecognizer {
         match (&self.state, &new_state) {
             (GestureRecognizerState::PinchPanGesture(previous_state),
              GestureRecognizerState::PinchPanGesture(gesture_state)) => {
-                 let zoom_event = Event::GestureZoom {
-                     zoom: gesture_state.zoom - previous_state.zoom,
-                     center: gesture_state.center(),
-                 };
                  let pan_event = Event::GesturePan(
                      previous_state.center().to_vec2() -  gesture_state.center().to_vec2()
                  );
-
                  gesture_events.push_back(pan_event);
-                 gesture_events.push_back(zoom_event);
+
+                 // Zoom and rotation are only defined for exactly two fingers (in
+                 // both this frame and the previous one); with more, the gesture
+                 // degrades gracefully to a plain centroid pan.
+                 let is_two_finger = previous_state.current_positions.len() == 2
+                     && gesture_state.current_positions.len() == 2;
+
+                 if is_two_finger &&
+                    (self.mode == GestureMode::PanZoom || self.mode == GestureMode::PanZoomRotate) {
+                     let zoom_event = Event::GestureZoom {
+                         zoom: gesture_state.zoom - previous_state.zoom,
+                         center: gesture_state.center(),
+                     };
+                     gesture_events.push_back(zoom_event);
+                 }
+
+                 if is_two_finger && self.mode == GestureMode::PanZoomRotate {
+                     let rotate_event = Event::GestureRotate {
+                         angle: normalize_angle(gesture_state.angle - previous_state.angle),
+                         center: gesture_state.center(),
+                     };
+                     gesture_events.push_back(rotate_event);
+                 }
+            },
+            (GestureRecognizerState::OneFingerIdle(_), GestureRecognizerState::OneFingerTap(tapped)) => {
+                // Emitted immediately on every release, mirroring native
+                // click/dblclick: a `count: 1` tap fires right away, and if a
+                // second tap follows within `TAP_DELAY` a further `count: 2`
+                // event fires for it. Widgets that only care about a genuine
+                // single tap (as opposed to the first half of a double-tap)
+                // need to debounce `count: 1` themselves against `TAP_DELAY`.
+                gesture_events.push_back(Event::GestureTap {
+                    pos: tapped.start_pos,
+                    count: tapped.tap_count,
+                });
+            },
+            (GestureRecognizerState::PinchPanGesture(gesture_state), GestureRecognizerState::Idle) => {
+                if let Some(velocity) = removed_queue.as_ref().and_then(compute_velocity) {
+                    gesture_events.push_back(Event::GestureFling {
+                        velocity,
+                        center: gesture_state.center(),
+                    });
+                }
             },
             _ => {}
         }
@@ -232,4 +552,188 @@ impl GestureRecognizer for DruidGestureRecognizer {
 
         gesture_events
     }
+
+    fn tick(&mut self, now: Instant) -> VecDeque<Event> {
+        let mut gesture_events = VecDeque::<Event>::new();
+
+        match &self.state {
+            GestureRecognizerState::OneFingerIdle(gesture_state)
+                if now.duration_since(gesture_state.start_time) >= LONG_PRESS_DELAY =>
+            {
+                gesture_events.push_back(Event::GestureLongPress {
+                    pos: gesture_state.start_pos,
+                });
+                self.state = GestureRecognizerState::OneFingerPressed(gesture_state.clone());
+            },
+            GestureRecognizerState::OneFingerTap(gesture_state)
+                if now.duration_since(gesture_state.start_time) >= TAP_DELAY =>
+            {
+                self.state = GestureRecognizerState::Idle;
+            },
+            _ => {}
+        }
+
+        gesture_events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `PointerId`/`PointerEvent` are defined outside this module; these helpers
+    // only rely on the `id`/`pos` fields this file itself reads from them.
+    fn down(id: u64, pos: (f64, f64)) -> Event {
+        Event::PointerDown(PointerEvent { id: PointerId(id), pos: Point::new(pos.0, pos.1) })
+    }
+
+    fn moved(id: u64, pos: (f64, f64)) -> Event {
+        Event::PointerMove(PointerEvent { id: PointerId(id), pos: Point::new(pos.0, pos.1) })
+    }
+
+    fn up(id: u64, pos: (f64, f64)) -> Event {
+        Event::PointerUp(PointerEvent { id: PointerId(id), pos: Point::new(pos.0, pos.1) })
+    }
+
+    fn find_tap_count(events: &VecDeque<Event>) -> Option<u32> {
+        events.iter().find_map(|e| match e {
+            Event::GestureTap { count, .. } => Some(*count),
+            _ => None,
+        })
+    }
+
+    fn find_fling_velocity(events: &VecDeque<Event>) -> Option<Vec2> {
+        events.iter().find_map(|e| match e {
+            Event::GestureFling { velocity, .. } => Some(*velocity),
+            _ => None,
+        })
+    }
+
+    fn find_zoom(events: &VecDeque<Event>) -> Option<f64> {
+        events.iter().find_map(|e| match e {
+            Event::GestureZoom { zoom, .. } => Some(*zoom),
+            _ => None,
+        })
+    }
+
+    fn find_rotate(events: &VecDeque<Event>) -> Option<f64> {
+        events.iter().find_map(|e| match e {
+            Event::GestureRotate { angle, .. } => Some(*angle),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn two_finger_pinch_emits_zoom_and_rotate() {
+        let mut recognizer = DruidGestureRecognizer::new(GestureMode::PanZoomRotate);
+
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+        recognizer.process_event(&down(2, (100.0, 0.0)));
+        // Move finger one enough to leave `TwoFingersIdle` and start pinching.
+        recognizer.process_event(&moved(1, (0.0, -30.0)));
+        // Fingers spread apart and rotate relative to their starting positions.
+        let events = recognizer.process_event(&moved(2, (100.0, 100.0)));
+
+        let zoom = find_zoom(&events).expect("expected a GestureZoom event");
+        assert!(zoom > 0.0, "fingers spread apart, expected zoom > 0, got {}", zoom);
+
+        let rotate = find_rotate(&events).expect("expected a GestureRotate event");
+        assert!(rotate.abs() > 0.1, "fingers rotated, expected a non-trivial angle, got {}", rotate);
+
+        assert!(events.iter().any(|e| matches!(e, Event::GesturePan(_))));
+    }
+
+    #[test]
+    fn third_finger_joining_degrades_to_centroid_pan() {
+        let mut recognizer = DruidGestureRecognizer::new(GestureMode::PanZoomRotate);
+
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+        recognizer.process_event(&down(2, (100.0, 0.0)));
+        recognizer.process_event(&moved(1, (0.0, -30.0)));
+
+        // A third finger joins mid-gesture; zoom/rotation become undefined and
+        // the gesture should degrade to panning the centroid instead of panicking.
+        recognizer.process_event(&down(3, (50.0, 50.0)));
+        let events = recognizer.process_event(&moved(1, (0.0, -80.0)));
+
+        assert!(find_zoom(&events).is_none(), "zoom is undefined for three fingers");
+        assert!(find_rotate(&events).is_none(), "rotation is undefined for three fingers");
+        assert!(events.iter().any(|e| matches!(e, Event::GesturePan(_))));
+    }
+
+    #[test]
+    fn pointer_vanishing_mid_gesture_does_not_panic() {
+        let mut recognizer = DruidGestureRecognizer::new(GestureMode::PanZoomRotate);
+
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+        recognizer.process_event(&down(2, (100.0, 0.0)));
+        recognizer.process_event(&moved(1, (0.0, -30.0)));
+
+        // Finger two vanishes without an Up/Leave event reaching the recognizer
+        // (e.g. a dropped event further up the pipeline).
+        recognizer.pointers_track.remove(&PointerId(2));
+        recognizer.pointer_order.retain(|id| id != &PointerId(2));
+
+        let events = recognizer.process_event(&moved(1, (0.0, -40.0)));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn second_tap_within_tap_delay_promotes_to_double_tap() {
+        let mut recognizer = DruidGestureRecognizer::new(GestureMode::PanOnly);
+
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+        let first_tap = recognizer.process_event(&up(1, (0.0, 0.0)));
+        assert_eq!(find_tap_count(&first_tap), Some(1));
+
+        // Same finger taps again, well within `TAP_DELAY`.
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+        let second_tap = recognizer.process_event(&up(1, (0.0, 0.0)));
+        assert_eq!(find_tap_count(&second_tap), Some(2));
+    }
+
+    #[test]
+    fn held_pointer_fires_long_press_on_tick() {
+        let mut recognizer = DruidGestureRecognizer::new(GestureMode::PanOnly);
+
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+        let events = recognizer.tick(Instant::now() + LONG_PRESS_DELAY + Duration::from_millis(100));
+
+        assert!(events.iter().any(|e| matches!(e, Event::GestureLongPress { pos } if *pos == Point::new(0.0, 0.0))));
+    }
+
+    #[test]
+    fn releasing_after_a_pinch_pan_emits_fling_velocity() {
+        let mut recognizer = DruidGestureRecognizer::new(GestureMode::PanOnly);
+
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+        recognizer.process_event(&down(2, (100.0, 0.0)));
+        // Exceed the pinch threshold to enter `PinchPanGesture`.
+        recognizer.process_event(&moved(1, (0.0, -30.0)));
+        // A real gap between samples is needed so the velocity fit below has a
+        // non-zero elapsed time to divide by.
+        std::thread::sleep(Duration::from_millis(20));
+        recognizer.process_event(&moved(1, (50.0, -30.0)));
+
+        let events = recognizer.process_event(&up(1, (50.0, -30.0)));
+
+        let velocity = find_fling_velocity(&events).expect("expected a GestureFling event");
+        assert!(velocity.x > 0.0, "finger moved in +x, expected a positive x velocity, got {:?}", velocity);
+    }
+
+    #[test]
+    fn pointer_captured_by_another_widget_is_not_admitted() {
+        let mut recognizer = DruidGestureRecognizer::new(GestureMode::PanOnly);
+
+        recognizer.capture(PointerId(1), WidgetId(10));
+        recognizer.process_event(&down(1, (0.0, 0.0)));
+
+        // A second pointer claimed by a different widget must not join this
+        // gesture, even though nothing has released pointer one yet.
+        recognizer.capture(PointerId(2), WidgetId(20));
+        recognizer.process_event(&down(2, (50.0, 50.0)));
+
+        assert!(!recognizer.pointers_track.contains_key(&PointerId(2)));
+        assert_eq!(recognizer.pointer_order, vec![PointerId(1)]);
+    }
 }